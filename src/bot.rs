@@ -0,0 +1,224 @@
+use crate::shows::{Category, Show, get_shows_by_category};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use urlencoding::encode;
+
+const TELEGRAM_BASE_URL: &str = "https://api.telegram.org";
+const SUBS_FILE_NAME: &str = "subscriptions.json";
+
+/// How many shows `/upcoming` and the per-category commands list at once.
+const LIST_LIMIT: usize = 10;
+
+/// The persisted chat → subscribed categories map.
+///
+/// Stored as JSON in [`SUBS_FILE_NAME`] so registrations survive restarts.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Subscriptions {
+    chats: HashMap<i64, Vec<Category>>,
+}
+
+impl Subscriptions {
+    /// Loads the subscription map, returning an empty one if no file exists yet.
+    fn load() -> Result<Self> {
+        match std::fs::read_to_string(SUBS_FILE_NAME) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(_) => Ok(Subscriptions::default()),
+        }
+    }
+
+    /// Persists the subscription map to disk.
+    fn save(&self) -> Result<()> {
+        std::fs::write(SUBS_FILE_NAME, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Registers `chat_id` for `category`, ignoring duplicate registrations.
+    fn subscribe(&mut self, chat_id: i64, category: Category) {
+        let entry = self.chats.entry(chat_id).or_default();
+        if !entry.contains(&category) {
+            entry.push(category);
+        }
+    }
+}
+
+/// A Telegram `getUpdates` response envelope.
+#[derive(Debug, Deserialize)]
+struct UpdatesResponse {
+    result: Vec<Update>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    text: Option<String>,
+    chat: Chat,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+/// Runs the long-polling bot loop until the process is killed.
+///
+/// # Returns
+/// * `Result<()>` - Never returns `Ok` in normal operation; propagates an error
+///   if the Telegram token is missing or a poll request fails fatally.
+///
+/// # Errors
+/// * Returns an error if the `TELEGRAM_TOKEN` environment variable is unset.
+///
+/// # Behavior
+/// * Polls `getUpdates` with an `offset` cursor so each update is processed once.
+/// * Dispatches `/upcoming`, `/comedy`, `/music` and `/subscribe <category>`
+///   commands, replying to the originating chat via the Telegram API.
+pub async fn run() -> Result<()> {
+    let token = env::var("TELEGRAM_TOKEN")?;
+    let client = reqwest::Client::new();
+    let mut offset: i64 = 0;
+
+    println!("Bot mode: polling for updates...");
+    loop {
+        let updates = get_updates(&client, &token, offset).await?;
+        for update in updates {
+            offset = offset.max(update.update_id + 1);
+            if let Some(message) = update.message {
+                handle_message(&client, &token, message).await?;
+            }
+        }
+    }
+}
+
+/// Notifies every chat subscribed to a show's category that it is now listed.
+///
+/// # Arguments
+/// * `new_shows` - The shows detected as newly-added on this run.
+///
+/// # Returns
+/// * `Result<()>` - `Ok(())` once all matching subscribers have been messaged.
+///
+/// # Errors
+/// * Returns an error if the `TELEGRAM_TOKEN` is unset or a send fails.
+pub async fn notify_subscribers(new_shows: &[Show]) -> Result<()> {
+    let subs = Subscriptions::load()?;
+    if subs.chats.is_empty() {
+        return Ok(());
+    }
+
+    let client = reqwest::Client::new();
+    let token = env::var("TELEGRAM_TOKEN")?;
+    for (&chat_id, categories) in &subs.chats {
+        let matching: Vec<&Show> = new_shows
+            .iter()
+            .filter(|s| categories.contains(&s.category))
+            .collect();
+        if matching.is_empty() {
+            continue;
+        }
+        let body = matching
+            .iter()
+            .map(|s| format!("{} ({}) — {} {}", s.title, s.category, s.date, s.time))
+            .collect::<Vec<_>>()
+            .join("\n");
+        send_message(&client, &token, chat_id, &format!("הופעות חדשות:\n{}", body)).await?;
+    }
+    Ok(())
+}
+
+/// Fetches a batch of updates, long-polling for up to 30 seconds; the batch is
+/// empty if none arrived in that time.
+async fn get_updates(client: &reqwest::Client, token: &str, offset: i64) -> Result<Vec<Update>> {
+    let url = format!(
+        "{}/bot{}/getUpdates?timeout=30&offset={}",
+        TELEGRAM_BASE_URL, token, offset
+    );
+    let resp: UpdatesResponse = client.get(&url).send().await?.json().await?;
+    Ok(resp.result)
+}
+
+/// Dispatches a single incoming message to its command handler.
+async fn handle_message(client: &reqwest::Client, token: &str, message: Message) -> Result<()> {
+    let chat_id = message.chat.id;
+    let text = match message.text {
+        Some(text) => text,
+        None => return Ok(()),
+    };
+
+    let mut parts = text.split_whitespace();
+    let reply = match parts.next() {
+        Some("/upcoming") => list_shows(None).await?,
+        Some("/comedy") => list_shows(Some(Category::Comedy)).await?,
+        Some("/music") => list_shows(Some(Category::Music)).await?,
+        Some("/subscribe") => match parts.next().map(parse_category) {
+            Some(Some(category)) => {
+                let mut subs = Subscriptions::load()?;
+                subs.subscribe(chat_id, category);
+                subs.save()?;
+                format!("Subscribed to {} alerts.", category)
+            }
+            _ => "Usage: /subscribe comedy|music".to_string(),
+        },
+        _ => "Commands: /upcoming, /comedy, /music, /subscribe comedy|music".to_string(),
+    };
+
+    send_message(client, token, chat_id, &reply).await
+}
+
+/// Builds a reply listing the next [`LIST_LIMIT`] shows, optionally filtered.
+async fn list_shows(filter: Option<Category>) -> Result<String> {
+    let mut shows = match filter {
+        Some(category) => get_shows_by_category(category).await?,
+        None => {
+            let mut all = get_shows_by_category(Category::Comedy).await?;
+            all.extend(get_shows_by_category(Category::Music).await?);
+            all
+        }
+    };
+    shows.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.time.cmp(&b.time)));
+
+    if shows.is_empty() {
+        return Ok("No upcoming shows.".to_string());
+    }
+
+    let body = shows
+        .iter()
+        .take(LIST_LIMIT)
+        .map(|s| format!("{} ({}) — {} {}", s.title, s.category, s.date, s.time))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(body)
+}
+
+/// Parses a category name from a command argument.
+fn parse_category(text: &str) -> Option<Category> {
+    match text.to_lowercase().as_str() {
+        "comedy" => Some(Category::Comedy),
+        "music" => Some(Category::Music),
+        _ => None,
+    }
+}
+
+/// Sends a plain-text message to `chat_id` via the Telegram API.
+async fn send_message(
+    client: &reqwest::Client,
+    token: &str,
+    chat_id: i64,
+    text: &str,
+) -> Result<()> {
+    let url = format!(
+        "{}/bot{}/sendMessage?chat_id={}&text={}",
+        TELEGRAM_BASE_URL,
+        token,
+        chat_id,
+        encode(text)
+    );
+    let _resp = client.get(&url).send().await?;
+    Ok(())
+}
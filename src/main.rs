@@ -1,12 +1,23 @@
+mod bot;
 mod shows;
+mod store;
 
 use crate::shows::Category;
 use crate::shows::{Show, get_shows_by_category};
+use crate::store::Store;
 use anyhow::Result;
+use chrono::{Local, NaiveDateTime, TimeZone};
 use std::env;
+use std::time::Duration;
 use urlencoding::encode;
 
 const FILE_NAME: &str = "shows.json";
+const ICAL_FILE_NAME: &str = "shows.ics";
+const RSS_FILE_NAME: &str = "shows.xml";
+const DB_FILE_NAME: &str = "shows.db";
+
+/// Lead windows, in minutes, at which a pre-show reminder is sent.
+const REMINDER_OFFSETS_MINUTES: [i64; 2] = [24 * 60, 60];
 
 #[derive(Debug, thiserror::Error)]
 enum HtmaError {
@@ -14,69 +25,197 @@ enum HtmaError {
     CategoryNotFound,
 }
 
-fn main() -> Result<()> {
+#[tokio::main]
+async fn main() -> Result<()> {
     println!("htma-scanner");
 
-    let prev_shows_vec = import_file().unwrap_or_else(|_| {
-        println!("No previous data found, fetching new shows...");
-        vec![]
-    });
+    // Optional long-running bot mode: poll Telegram and serve queries instead
+    // of running a single scan.
+    if env::args().any(|arg| arg == "--bot") {
+        return bot::run().await;
+    }
 
-    let current_shows = get_shows()?;
+    let store = Store::open(DB_FILE_NAME)?;
 
-    // Check if the new shows are different from the previous ones
-    if prev_shows_vec == current_shows {
+    // Optional daemon mode: re-scan on an interval and fire pre-show reminders.
+    if let Some(interval) = watch_interval() {
+        println!("Watch mode: scanning every {:?}", interval);
+        loop {
+            let shows = scan_once(&store).await?;
+            send_reminders(&store, &shows).await?;
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    let shows = scan_once(&store).await?;
+    send_reminders(&store, &shows).await?;
+    Ok(())
+}
+
+/// Parses the `--watch <interval>` flag, returning the configured interval.
+///
+/// # Returns
+/// * `Option<Duration>` - The interval if `--watch` was supplied, otherwise
+///   `None`.
+///
+/// # Behavior
+/// * Accepts a plain number of seconds or a value suffixed with `s`, `m` or `h`.
+/// * Defaults to one hour if the flag is present but its value is missing or
+///   unparseable.
+fn watch_interval() -> Option<Duration> {
+    let args: Vec<String> = env::args().collect();
+    let pos = args.iter().position(|arg| arg == "--watch")?;
+    let secs = args
+        .get(pos + 1)
+        .and_then(|value| parse_interval(value))
+        .unwrap_or(60 * 60);
+    Some(Duration::from_secs(secs))
+}
+
+/// Parses an interval string such as `3600`, `90m` or `2h` into seconds.
+fn parse_interval(value: &str) -> Option<u64> {
+    let (number, multiplier) = match value.chars().last() {
+        Some('s') => (&value[..value.len() - 1], 1),
+        Some('m') => (&value[..value.len() - 1], 60),
+        Some('h') => (&value[..value.len() - 1], 60 * 60),
+        _ => (value, 1),
+    };
+    number.parse::<u64>().ok().map(|n| n * multiplier)
+}
+
+/// Runs a single scan: fetch, diff against history, notify and export.
+///
+/// # Arguments
+/// * `store` - The SQLite-backed history store.
+///
+/// # Returns
+/// * `Result<Vec<Show>>` - The shows returned by the latest scrape, so callers
+///   can scope reminders to shows that are still listed.
+async fn scan_once(store: &Store) -> Result<Vec<Show>> {
+    let current_shows = get_shows().await?;
+
+    // Diff against the persisted scan history, which also records this run so
+    // the history survives across invocations.
+    let diff = store.diff_shows(&current_shows)?;
+
+    if diff.added.is_empty() && diff.disappeared.is_empty() {
         println!("No new show found.");
-        return Ok(());
+        return Ok(current_shows);
     }
 
-    if let Some(new_shows) = check_for_new_shows(prev_shows_vec, &current_shows) {
+    if !diff.added.is_empty() {
         println!("New shows found:");
-        for show in &new_shows {
+        for show in &diff.added {
+            println!("{}", show);
+        }
+    }
+    if !diff.disappeared.is_empty() {
+        println!("Shows removed:");
+        for show in &diff.disappeared {
             println!("{}", show);
         }
+    }
 
-        let msg = format!(
+    let mut sections = Vec::new();
+    if !diff.added.is_empty() {
+        sections.push(format!(
             "*הופעות חדשות*:\n{}",
-            new_shows
+            diff.added
                 .iter()
                 .map(|s| format!("{} 🔛 `{} ({})`", s.title, s.date, s.time))
                 .collect::<Vec<_>>()
                 .join("\r\n")
-        );
-        notify(msg)?;
+        ));
+    }
+    if !diff.disappeared.is_empty() {
+        sections.push(format!(
+            "*הופעות שהוסרו*:\n{}",
+            diff.disappeared
+                .iter()
+                .map(|s| format!("{} ❌ `{} ({})`", s.title, s.date, s.time))
+                .collect::<Vec<_>>()
+                .join("\r\n")
+        ));
+    }
+    notify(sections.join("\r\n\r\n")).await?;
 
-        // Export the new shows to a file
-        export_file(&current_shows)?;
-        println!("Saved to {}", FILE_NAME);
-    } else {
-        println!("No new show found.");
+    // Fan out to any chats that subscribed to the affected categories.
+    if !diff.added.is_empty() {
+        bot::notify_subscribers(&diff.added).await?;
     }
 
-    Ok(())
+    // Export the current shows to a file
+    export_file(&current_shows)?;
+    println!("Saved to {}", FILE_NAME);
+
+    // Also emit an iCalendar feed so the shows can be subscribed to.
+    export_ical(&current_shows)?;
+    println!("Saved to {}", ICAL_FILE_NAME);
+
+    // And an RSS 2.0 feed for feed readers.
+    export_rss(&current_shows)?;
+    println!("Saved to {}", RSS_FILE_NAME);
+
+    Ok(current_shows)
 }
 
-/// Compares two lists of shows and identifies new shows in the current list.
+/// Sends pre-show reminders for currently-listed shows entering a lead window.
 ///
 /// # Arguments
-/// * `prev_shows_vec` - A vector of `Show` objects representing the previous list of shows.
-/// * `current_shows` - A reference to a vector of `Show` objects representing the current list of shows.
+/// * `store` - The SQLite-backed history store, used to track fired reminders.
+/// * `shows` - The shows from the latest scrape, so cancelled/removed shows are
+///   never reminded about.
 ///
 /// # Returns
-/// * `Option<Vec<Show>>` - Returns `Some(Vec<Show>)` containing the new shows if there are any, or `None` if no new shows are found.
+/// * `Result<()>` - `Ok(())` once all due reminders have been sent.
 ///
 /// # Behavior
-/// * Retains only the shows in `current_shows` that are not present in `prev_shows_vec`.
-/// * Returns `None` if no new shows are found.
-fn check_for_new_shows(prev_shows_vec: Vec<Show>, current_shows: &Vec<Show>) -> Option<Vec<Show>> {
-    // Print only different shows
-    let mut new_shows = current_shows.clone();
-    new_shows.retain(|new_show| !prev_shows_vec.iter().any(|old_show| old_show == new_show));
-    if new_shows.is_empty() {
-        return None;
+/// * For each show and each offset in [`REMINDER_OFFSETS_MINUTES`], fires a
+///   reminder when the show starts within that window and the
+///   `(show_hash, offset)` pair has not already fired.
+/// * Records every fired pair so restarts do not double-notify.
+async fn send_reminders(store: &Store, shows: &[Show]) -> Result<()> {
+    let now = Local::now().naive_local();
+    let unix_now = Local::now().timestamp();
+
+    for show in shows {
+        let start = NaiveDateTime::new(show.date, show.time);
+        let minutes_until = (start - now).num_minutes();
+        if minutes_until < 0 {
+            continue;
+        }
+
+        for (i, offset) in REMINDER_OFFSETS_MINUTES.iter().copied().enumerate() {
+            // Each offset owns the band down to the next smaller offset, so a
+            // show first seen inside the 1h window does not also fire the 24h one.
+            let lower = REMINDER_OFFSETS_MINUTES.get(i + 1).copied().unwrap_or(0);
+            let hash = show.stable_hash();
+            if minutes_until <= offset
+                && minutes_until > lower
+                && !store.reminder_fired(hash, offset)?
+            {
+                let msg = format!(
+                    "🔔 {} at {}: {}",
+                    reminder_lead(offset),
+                    show.time.format("%H:%M"),
+                    show.title
+                );
+                notify(msg).await?;
+                store.mark_reminder(hash, offset, unix_now)?;
+            }
+        }
     }
 
-    Some(new_shows)
+    Ok(())
+}
+
+/// Returns the lead-time wording for a reminder offset, in minutes.
+fn reminder_lead(offset_minutes: i64) -> &'static str {
+    match offset_minutes {
+        o if o >= 24 * 60 => "Tomorrow",
+        o if o <= 60 => "In 1 hour",
+        _ => "Soon",
+    }
 }
 
 /// Retrieves a list of shows from multiple categories.
@@ -88,14 +227,18 @@ fn check_for_new_shows(prev_shows_vec: Vec<Show>, current_shows: &Vec<Show>) ->
 /// * Returns an error if fetching shows by category fails.
 ///
 /// # Behavior
-/// * Fetches shows from the `Comedy` and `Music` categories.
+/// * Fires the `Comedy` and `Music` category requests concurrently.
 /// * Combines the results into a single vector.
 /// * Sorts the shows by date and time in ascending order.
-fn get_shows() -> Result<Vec<Show>> {
-    let mut shows_vec = get_shows_by_category(Category::Comedy)?;
-    let music_vec = get_shows_by_category(Category::Music)?;
+async fn get_shows() -> Result<Vec<Show>> {
+    let categories = [Category::Comedy, Category::Music];
+    let results =
+        futures::future::join_all(categories.iter().map(|&c| get_shows_by_category(c))).await;
 
-    shows_vec.extend(music_vec);
+    let mut shows_vec = Vec::new();
+    for result in results {
+        shows_vec.extend(result?);
+    }
     // Sort the shows by date and by time
     shows_vec.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.time.cmp(&b.time)));
     Ok(shows_vec)
@@ -120,20 +263,177 @@ fn export_file(shows_vec: &Vec<Show>) -> Result<()> {
     Ok(())
 }
 
-/// Imports a list of `Show` objects from a JSON file.
+/// Exports a vector of `Show` objects as an iCalendar (`.ics`) feed.
+///
+/// # Arguments
+/// * `shows` - A slice of `Show` objects to be written as calendar events.
 ///
 /// # Returns
-/// * `Result<Vec<Show>>` - A vector of `Show` objects if successful, or an error if the operation fails.
+/// * `Result<()>` - Returns `Ok(())` if the operation is successful, or an error if it fails.
 ///
 /// # Errors
-/// * Returns an error if reading the file or deserializing the JSON fails.
-fn import_file() -> Result<Vec<Show>> {
-    // Read from a file
-    let json = std::fs::read_to_string(FILE_NAME)?;
+/// * Returns an error if writing to the file system fails.
+///
+/// # Behavior
+/// * Emits one `VEVENT` per `Show` with a `UID` derived from the show's stable
+///   hash, a `DTSTART` in floating local time and a default one-hour `DTEND`.
+/// * Escapes and folds text per RFC 5545 and terminates every line with CRLF.
+fn export_ical(shows: &[Show]) -> Result<()> {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//htma-scanner//EN".to_string(),
+    ];
 
-    // Deserialize JSON to Vec<Show>
-    let shows_vec: Vec<Show> = serde_json::from_str(&json)?;
-    Ok(shows_vec)
+    for show in shows {
+        let dtstart = show.date.format("%Y%m%d").to_string() + "T" + &show.time.format("%H%M%S").to_string();
+        let dtend = {
+            let end = show.time + chrono::Duration::hours(1);
+            // Roll over to the next day if the one-hour event crosses midnight.
+            let date = if end < show.time {
+                show.date + chrono::Duration::days(1)
+            } else {
+                show.date
+            };
+            date.format("%Y%m%d").to_string() + "T" + &end.format("%H%M%S").to_string()
+        };
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}@htma-scanner", show.stable_hash()));
+        lines.push(format!("DTSTART;VALUE=DATE-TIME:{}", dtstart));
+        lines.push(format!("DTEND;VALUE=DATE-TIME:{}", dtend));
+        lines.push(format!("SUMMARY:{}", escape_ical_text(&show.title)));
+        lines.push(format!("CATEGORIES:{}", show.category));
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let body: String = lines
+        .iter()
+        .map(|line| fold_ical_line(line) + "\r\n")
+        .collect();
+
+    std::fs::write(ICAL_FILE_NAME, body)?;
+    Ok(())
+}
+
+/// Escapes a text value for safe inclusion in an iCalendar property.
+///
+/// # Arguments
+/// * `text` - The raw text to escape.
+///
+/// # Returns
+/// * `String` - The text with backslashes, newlines, commas and semicolons
+///   escaped per RFC 5545.
+fn escape_ical_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+}
+
+/// Folds a content line so that no line exceeds 75 octets, per RFC 5545.
+///
+/// # Arguments
+/// * `line` - The already-escaped content line.
+///
+/// # Returns
+/// * `String` - The line with CRLF + single-space continuations inserted at
+///   75-octet boundaries.
+fn fold_ical_line(line: &str) -> String {
+    let bytes = line.as_bytes();
+    if bytes.len() <= 75 {
+        return line.to_string();
+    }
+
+    let mut folded = String::new();
+    let mut start = 0;
+    // The first line may use the full 75 octets; continuation lines reserve one
+    // octet for the leading space.
+    let mut width = 75;
+    while start < bytes.len() {
+        // Boundary is relative to the current chunk start, never an absolute index.
+        let mut end = (start + width).min(bytes.len());
+        // Never split inside a multi-byte UTF-8 sequence.
+        while end > start && end < bytes.len() && (bytes[end] & 0xC0) == 0x80 {
+            end -= 1;
+        }
+        if !folded.is_empty() {
+            folded.push_str("\r\n ");
+        }
+        folded.push_str(&line[start..end]);
+        start = end;
+        width = 74;
+    }
+    folded
+}
+
+/// Exports a vector of `Show` objects as an RSS 2.0 feed.
+///
+/// # Arguments
+/// * `shows` - A slice of `Show` objects to be published as feed items.
+///
+/// # Returns
+/// * `Result<()>` - Returns `Ok(())` if the operation is successful, or an error if it fails.
+///
+/// # Errors
+/// * Returns an error if writing to the file system fails.
+///
+/// # Behavior
+/// * Emits a single `<channel>` followed by one `<item>` per `Show`, with the
+///   show date/time formatted as an RFC 822 `<pubDate>` in local time and a
+///   stable-hash `<guid isPermaLink="false">`.
+/// * Escapes `&`, `<` and `>` in all text nodes.
+fn export_rss(shows: &[Show]) -> Result<()> {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    xml.push_str("\n<rss version=\"2.0\">\n<channel>\n");
+    xml.push_str("<title>htma shows</title>\n");
+    xml.push_str("<link>https://htma.smarticket.co.il/</link>\n");
+    xml.push_str("<description>Comedy and music shows scanned from htma</description>\n");
+
+    for show in shows {
+        let datetime = chrono::NaiveDateTime::new(show.date, show.time);
+        let pub_date = Local
+            .from_local_datetime(&datetime)
+            .single()
+            .map(|dt| dt.format("%a, %d %b %Y %H:%M:%S %z").to_string())
+            .unwrap_or_default();
+        let description = format!("{} at {} ({})", show.date, show.time, show.category);
+
+        xml.push_str("<item>\n");
+        xml.push_str(&format!("<title>{}</title>\n", escape_xml(&show.title)));
+        xml.push_str(&format!("<category>{}</category>\n", show.category));
+        xml.push_str(&format!("<pubDate>{}</pubDate>\n", pub_date));
+        xml.push_str(&format!(
+            "<guid isPermaLink=\"false\">{}</guid>\n",
+            show.stable_hash()
+        ));
+        xml.push_str(&format!(
+            "<description>{}</description>\n",
+            escape_xml(&description)
+        ));
+        xml.push_str("</item>\n");
+    }
+
+    xml.push_str("</channel>\n</rss>\n");
+
+    std::fs::write(RSS_FILE_NAME, xml)?;
+    Ok(())
+}
+
+/// Escapes the XML metacharacters `&`, `<` and `>` in a text node.
+///
+/// # Arguments
+/// * `text` - The raw text to escape.
+///
+/// # Returns
+/// * `String` - The text safe for inclusion in an XML element body.
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
 }
 
 /// Sends a notification message via Telegram.
@@ -151,8 +451,8 @@ fn import_file() -> Result<Vec<Show>> {
 /// # Behavior
 /// * Encodes the message text to ensure it is URL-safe.
 /// * Constructs the Telegram API URL using the bot token and chat ID.
-/// * Sends the message using a blocking HTTP GET request.
-fn notify(text: String) -> Result<()> {
+/// * Sends the message using an async HTTP GET request.
+async fn notify(text: String) -> Result<()> {
     const TELEGRAM_BASE_URL: &str = "https://api.telegram.org";
 
     let encoded = encode(&text);
@@ -165,7 +465,7 @@ fn notify(text: String) -> Result<()> {
         TELEGRAM_BASE_URL, token, chat_id, encoded
     );
 
-    let _resp = reqwest::blocking::get(&url)?;
+    let _resp = reqwest::get(&url).await?;
 
     Ok(())
 }
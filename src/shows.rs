@@ -5,6 +5,14 @@ use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+/// Per-request timeout applied to every HTTP fetch.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Number of attempts before a fetch gives up, with exponential backoff.
+const MAX_ATTEMPTS: u32 = 3;
 
 // Create a static HashMap that's initialized on first access
 static ENDPOINT_URLS: Lazy<HashMap<Category, &'static str>> = Lazy::new(|| {
@@ -54,6 +62,24 @@ impl Show {
             category: Category::None,
         }
     }
+
+    /// Computes a stable identifier for the show derived from its title, date
+    /// and time.
+    ///
+    /// # Returns
+    /// * `u64` - A hash that stays constant across runs for the same show,
+    ///   suitable for use as an iCalendar `UID` or a database key.
+    ///
+    /// # Behavior
+    /// * Ignores `category`, so the same show listed under two categories still
+    ///   collapses to a single identity.
+    pub fn stable_hash(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.title.hash(&mut hasher);
+        self.date.hash(&mut hasher);
+        self.time.hash(&mut hasher);
+        hasher.finish()
+    }
 }
 
 impl Display for Show {
@@ -123,12 +149,26 @@ fn parse_hebrew_date(date_str: &str) -> anyhow::Result<NaiveDate> {
 /// # Returns
 /// * `Ok(Vec<Show>)` - A vector of `Show` objects if successful.
 /// * `Err(Error)` - An error if the HTML parsing or data extraction fails.
-pub fn get_shows_by_category(category: Category) -> anyhow::Result<Vec<Show>> {
-    let mut ret_vec = Vec::new();
+pub async fn get_shows_by_category(category: Category) -> anyhow::Result<Vec<Show>> {
+    let html = get_html_by_category(category).await?;
+    parse_shows(&html, category)
+}
 
-    let html = get_html_by_category(category)?;
+/// Parses the shows out of a category listing page.
+///
+/// # Arguments
+/// * `html` - The raw HTML of the category listing page.
+/// * `category` - The category the page belongs to.
+///
+/// # Returns
+/// * `Ok(Vec<Show>)` - The parsed shows.
+/// * `Err(Error)` - An error if a date or time field fails to parse. Parse
+///   errors are never retried. A page without the `category_shows` container
+///   panics rather than returning an error.
+fn parse_shows(html: &str, category: Category) -> anyhow::Result<Vec<Show>> {
+    let mut ret_vec = Vec::new();
 
-    let document = Html::parse_document(&html);
+    let document = Html::parse_document(html);
     let selector = Selector::parse(r#"div[class="category_shows"]"#).unwrap();
     let shows_element = document.select(&selector).next().unwrap();
 
@@ -185,10 +225,42 @@ fn get_url(category: Category) -> anyhow::Result<&'static str> {
 ///
 /// # Returns
 /// * `Ok(String)` - The HTML content as a string if the request is successful.
-/// * `Err(Error)` - An error if the URL retrieval or HTTP request fails.
-fn get_html_by_category(category: Category) -> anyhow::Result<String> {
+/// * `Err(Error)` - An error if the URL retrieval or HTTP request fails after
+///   all retries.
+///
+/// # Behavior
+/// * Builds a `reqwest::Client` with a 15-second per-request timeout.
+/// * Makes up to [`MAX_ATTEMPTS`] attempts, retrying network/timeout failures
+///   with exponential backoff (1s, then 2s).
+async fn get_html_by_category(category: Category) -> anyhow::Result<String> {
     let url = get_url(category)?;
-    let body = reqwest::blocking::get(url)?.text()?;
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
 
-    Ok(body)
+    let mut delay = Duration::from_secs(1);
+    for attempt in 1..=MAX_ATTEMPTS {
+        match fetch(&client, url).await {
+            Ok(body) => return Ok(body),
+            Err(err) if attempt < MAX_ATTEMPTS => {
+                eprintln!(
+                    "Fetch for {} failed (attempt {}/{}): {}; retrying in {:?}",
+                    category, attempt, MAX_ATTEMPTS, err, delay
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    unreachable!("retry loop always returns on the final attempt")
+}
+
+/// Performs a single HTTP GET, returning the response body.
+///
+/// Only network-level failures surface here; they are the ones the caller
+/// retries.
+async fn fetch(client: &reqwest::Client, url: &str) -> reqwest::Result<String> {
+    client.get(url).send().await?.text().await
 }
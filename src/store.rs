@@ -0,0 +1,224 @@
+use crate::shows::{Category, Show};
+use anyhow::Result;
+use chrono::Local;
+use rusqlite::Connection;
+use std::collections::HashSet;
+
+/// The result of diffing a fresh scrape against the persisted scan history.
+///
+/// * `added` - Shows not active on the previous run: listed for the first time,
+///   or listed again after having disappeared.
+/// * `unchanged` - Shows that were already active on the previous run.
+/// * `disappeared` - Shows stored last run but absent now (likely sold out or cancelled).
+#[derive(Debug, Default)]
+pub struct ShowDiff {
+    pub added: Vec<Show>,
+    pub unchanged: Vec<Show>,
+    pub disappeared: Vec<Show>,
+}
+
+/// A SQLite-backed store that keeps the full history of scraped shows.
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Opens (creating if necessary) the SQLite store at `path`.
+    ///
+    /// # Arguments
+    /// * `path` - Filesystem path of the SQLite database file.
+    ///
+    /// # Returns
+    /// * `Result<Store>` - The opened store, or an error if the connection or
+    ///   schema creation fails.
+    ///
+    /// # Errors
+    /// * Returns an error if the database cannot be opened or the schema cannot
+    ///   be created.
+    pub fn open(path: &str) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS shows (
+                hash       INTEGER PRIMARY KEY,
+                title      TEXT NOT NULL,
+                date       TEXT NOT NULL,
+                time       TEXT NOT NULL,
+                category   TEXT NOT NULL,
+                first_seen INTEGER NOT NULL,
+                last_seen  INTEGER NOT NULL,
+                active     INTEGER NOT NULL DEFAULT 1
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS reminders (
+                hash     INTEGER NOT NULL,
+                offset   INTEGER NOT NULL,
+                fired_at INTEGER NOT NULL,
+                PRIMARY KEY (hash, offset)
+            )",
+            [],
+        )?;
+        Ok(Store { conn })
+    }
+
+    /// Diffs the current scrape against the stored history and records it.
+    ///
+    /// # Arguments
+    /// * `current` - The shows produced by the latest scrape.
+    ///
+    /// # Returns
+    /// * `Result<ShowDiff>` - The newly-added, unchanged and disappeared shows.
+    ///
+    /// # Errors
+    /// * Returns an error if any SQLite read or write fails.
+    ///
+    /// # Behavior
+    /// * Collapses shows sharing a stable hash (e.g. the same show listed under
+    ///   two categories) to a single identity so they are classified once.
+    /// * Upserts every current show, stamping `first_seen` on insert, always
+    ///   refreshing `last_seen`, and (re)marking the row active.
+    /// * Classifies each current show as added (not active last run) or
+    ///   unchanged, and reports any active stored show absent from `current` as
+    ///   disappeared — flagging those rows inactive rather than deleting them, so
+    ///   their `first_seen`/`last_seen` history is retained and a removal is
+    ///   announced exactly once.
+    pub fn diff_shows(&self, current: &[Show]) -> Result<ShowDiff> {
+        let now = Local::now().timestamp();
+        let active = self.load_active()?;
+
+        let mut diff = ShowDiff::default();
+        let mut seen = HashSet::new();
+        for show in current {
+            let hash = show.stable_hash();
+            // Same title/date/time under multiple categories collapses to one row.
+            if !seen.insert(hash) {
+                continue;
+            }
+            if active.iter().any(|s| s.stable_hash() == hash) {
+                diff.unchanged.push(show.clone());
+            } else {
+                diff.added.push(show.clone());
+            }
+            self.upsert(show, now)?;
+        }
+
+        diff.disappeared = active
+            .into_iter()
+            .filter(|s| !seen.contains(&s.stable_hash()))
+            .collect();
+
+        for show in &diff.disappeared {
+            self.mark_removed(show.stable_hash(), now)?;
+        }
+
+        Ok(diff)
+    }
+
+    /// Reports whether a reminder has already fired for a `(show, offset)` pair.
+    ///
+    /// # Arguments
+    /// * `hash` - The show's stable hash.
+    /// * `offset_minutes` - The lead time, in minutes, of the reminder.
+    ///
+    /// # Returns
+    /// * `Result<bool>` - `true` if the reminder has already been recorded.
+    pub fn reminder_fired(&self, hash: u64, offset_minutes: i64) -> Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM reminders WHERE hash = ?1 AND offset = ?2",
+            rusqlite::params![hash as i64, offset_minutes],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Records that a reminder has fired for a `(show, offset)` pair so that a
+    /// restart does not re-send it.
+    ///
+    /// # Arguments
+    /// * `hash` - The show's stable hash.
+    /// * `offset_minutes` - The lead time, in minutes, of the reminder.
+    /// * `now` - The Unix timestamp at which the reminder fired.
+    pub fn mark_reminder(&self, hash: u64, offset_minutes: i64, now: i64) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO reminders (hash, offset, fired_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![hash as i64, offset_minutes, now],
+        )?;
+        Ok(())
+    }
+
+    /// Flags a show inactive once it is no longer listed, retaining its row (and
+    /// thus its `first_seen`/`last_seen` history) so it is reported disappeared
+    /// exactly once. Its fired-reminder records are deleted, so its reminders
+    /// can fire again if it is listed again.
+    ///
+    /// # Arguments
+    /// * `hash` - The show's stable hash.
+    /// * `now` - The Unix timestamp at which the show was found removed.
+    fn mark_removed(&self, hash: u64, now: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE shows SET active = 0, last_seen = ?2 WHERE hash = ?1",
+            rusqlite::params![hash as i64, now],
+        )?;
+        self.conn.execute(
+            "DELETE FROM reminders WHERE hash = ?1",
+            rusqlite::params![hash as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Loads every currently-active stored show.
+    fn load_active(&self) -> Result<Vec<Show>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT title, date, time, category FROM shows WHERE active = 1")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+
+        let mut shows = Vec::new();
+        for row in rows {
+            let (title, date, time, category) = row?;
+            shows.push(Show {
+                title,
+                date: date.parse()?,
+                time: time.parse()?,
+                category: parse_category(&category),
+            });
+        }
+        Ok(shows)
+    }
+
+    /// Inserts `show`, or refreshes `last_seen` and re-activates it if it is
+    /// already stored (preserving the original `first_seen`).
+    fn upsert(&self, show: &Show, now: i64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO shows (hash, title, date, time, category, first_seen, last_seen, active)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6, 1)
+             ON CONFLICT(hash) DO UPDATE SET last_seen = ?6, active = 1",
+            rusqlite::params![
+                show.stable_hash() as i64,
+                show.title,
+                show.date.to_string(),
+                show.time.to_string(),
+                show.category.to_string(),
+                now,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// Parses a `Category` from its `Display` value, falling back to `None`.
+fn parse_category(text: &str) -> Category {
+    match text {
+        "Comedy" => Category::Comedy,
+        "Music" => Category::Music,
+        _ => Category::None,
+    }
+}